@@ -1,9 +1,9 @@
 // Standard
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 // External
 use fixedbitset::FixedBitSet;
 // External (WASM)
-use js_sys;
 use wasm_bindgen::prelude::*;
 use web_sys::console;
 // Internal
@@ -34,11 +34,130 @@ pub enum UniverseObjects {
     Glider = 3,
 }
 
+// Scoped console.time/timeEnd guard. Logs the elapsed time for whatever
+// happens between `Timer::new` and the end of its scope. Only compiled in
+// when the `timing` feature is enabled, so it costs nothing otherwise.
+#[cfg(feature = "timing")]
+struct Timer {
+    name: &'static str,
+}
+
+#[cfg(feature = "timing")]
+impl Timer {
+    fn new(name: &'static str) -> Timer {
+        console::time_with_label(name);
+        Timer { name }
+    }
+}
+
+#[cfg(feature = "timing")]
+impl Drop for Timer {
+    fn drop(&mut self) {
+        console::time_end_with_label(self.name);
+    }
+}
+
+// A B/S-notation Life rule, e.g. "B3/S23" (Conway) or "B36/S23" (HighLife),
+// reduced to the two neighbour-count lookup tables `tick` needs.
+#[derive(Clone)]
+struct Rule {
+    born: [bool; 9],
+    survive: [bool; 9],
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        parse_rule("B3/S23")
+    }
+}
+
+impl Rule {
+    // Reconstructs the B/S rulestring `parse_rule` would produce these
+    // tables from, so it can be round-tripped through `to_rle`/`load_rle`.
+    fn to_rule_string(&self) -> String {
+        let born: String = (0..9)
+            .filter(|&n| self.born[n])
+            .map(|n| n.to_string())
+            .collect();
+        let survive: String = (0..9)
+            .filter(|&n| self.survive[n])
+            .map(|n| n.to_string())
+            .collect();
+        format!("B{}/S{}", born, survive)
+    }
+}
+
+fn parse_rule(rule: &str) -> Rule {
+    let mut born = [false; 9];
+    let mut survive = [false; 9];
+
+    let parts: Vec<&str> = rule.splitn(2, '/').collect();
+    if parts.len() != 2 {
+        log!("invalid rule string '{}', falling back to B3/S23", rule);
+        return Rule::default();
+    }
+
+    for part in parts.iter() {
+        if part.is_empty() {
+            log!("invalid rule string '{}', falling back to B3/S23", rule);
+            return Rule::default();
+        }
+        let (tag, digits) = part.split_at(1);
+        let table = match tag {
+            "B" | "b" => &mut born,
+            "S" | "s" => &mut survive,
+            _ => {
+                log!("invalid rule segment '{}', ignoring", part);
+                continue;
+            }
+        };
+        for digit in digits.chars().filter_map(|c| c.to_digit(10)) {
+            if (digit as usize) < table.len() {
+                table[digit as usize] = true;
+            }
+        }
+    }
+
+    Rule { born, survive }
+}
+
+// A small, pure-Rust xorshift64 PRNG. Not cryptographically secure, but
+// deterministic given a seed, which is all universe randomization needs:
+// two `Universe`s seeded alike tick through an identical history, with no
+// dependency on the browser's `Math.random`.
+#[derive(Clone)]
+struct XorShiftRng {
+    state: u64,
+}
+
+impl XorShiftRng {
+    fn new(seed: u64) -> XorShiftRng {
+        XorShiftRng {
+            // xorshift is undefined for a zero state, so nudge it off zero.
+            state: if seed == 0 { 0x9e3779b97f4a7c15 } else { seed },
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.state = x;
+        x
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+    }
+}
+
 fn build_cells(
     bitset: FixedBitSet,
     width: u32,
     height: u32,
     build_type: UniverseContents,
+    rng: &mut XorShiftRng,
 ) -> FixedBitSet {
     let mut new_cells = bitset.clone();
     match build_type {
@@ -66,7 +185,7 @@ fn build_cells(
         UniverseContents::Random => {
             log!("building random universe");
             for i in 0..width * height {
-                new_cells.set(i as usize, js_sys::Math::random() > 0.5);
+                new_cells.set(i as usize, rng.next_f64() > 0.5);
             }
         }
         UniverseContents::Lines => {
@@ -85,12 +204,19 @@ fn build_cells(
     new_cells
 }
 
+// Default seed used whenever a `Universe` is created without an explicit one.
+const DEFAULT_SEED: u64 = 0x2545_f491_4f6c_dd1d;
+
 #[wasm_bindgen]
 #[derive(Clone)]
 pub struct Universe {
     width: u32,
     height: u32,
     cells: FixedBitSet,
+    rule: Rule,
+    rng: XorShiftRng,
+    sparse_mode: bool,
+    live_cells: HashSet<usize>,
 }
 
 #[wasm_bindgen]
@@ -104,16 +230,52 @@ impl Universe {
 
         let size = (width * height) as usize;
         let bitset = FixedBitSet::with_capacity(size);
+        let mut rng = XorShiftRng::new(DEFAULT_SEED);
+
+        Universe {
+            width,
+            height,
+            // cells: build_cells(bitset, width, height, UniverseContents::Random, &mut rng),
+            cells: build_cells(bitset, width, height, UniverseContents::Lines, &mut rng),
+            // cells: build_cells(bitset, width, height, UniverseContents::Spaceship, &mut rng),
+            rule: Rule::default(),
+            rng,
+            sparse_mode: false,
+            live_cells: HashSet::new(),
+        }
+    }
+
+    // Like `new`, but seeds the random-universe generator explicitly and
+    // immediately randomizes, so the same seed always reproduces the same
+    // starting board.
+    pub fn new_with_seed(seed: u64) -> Universe {
+        utils::set_panic_hook();
+
+        let width = 80;
+        let height = 64;
+
+        let size = (width * height) as usize;
+        let bitset = FixedBitSet::with_capacity(size);
+        let mut rng = XorShiftRng::new(seed);
+        let cells = build_cells(bitset, width, height, UniverseContents::Random, &mut rng);
 
         Universe {
             width,
             height,
-            // cells: build_cells(bitset, width, height, UniverseContents::Random),
-            cells: build_cells(bitset, width, height, UniverseContents::Lines),
-            // cells: build_cells(bitset, width, height, UniverseContents::Spaceship),
+            cells,
+            rule: Rule::default(),
+            rng,
+            sparse_mode: false,
+            live_cells: HashSet::new(),
         }
     }
 
+    // Accepts a standard Life rulestring, e.g. "B3/S23" (Conway), "B36/S23"
+    // (HighLife), or "B2/S" (Seeds).
+    pub fn set_rule(&mut self, rule: &str) {
+        self.rule = parse_rule(rule);
+    }
+
     pub fn render(&self) -> String {
         self.to_string()
     }
@@ -132,48 +294,219 @@ impl Universe {
         self.create(object_type, row, col);
     }
 
+    // Loads an RLE-encoded pattern (the format used throughout the Life
+    // pattern ecosystem, e.g. LifeWiki), stamping its live cells starting at
+    // `(row, col)` and wrapping toroidally.
+    pub fn load_rle(&mut self, rle: &str, row: u32, col: u32) {
+        let mut header_seen = false;
+        let mut cur_row = 0;
+        let mut cur_col = 0;
+        let mut count_buf = String::new();
+
+        'lines: for line in rle.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if !header_seen {
+                header_seen = true;
+                for field in line.split(',') {
+                    let mut kv = field.splitn(2, '=');
+                    let key = kv.next().unwrap_or("").trim();
+                    let value = kv.next().unwrap_or("").trim();
+                    if key == "rule" {
+                        self.set_rule(value);
+                    }
+                }
+                continue;
+            }
+
+            for ch in line.chars() {
+                match ch {
+                    '0'..='9' => count_buf.push(ch),
+                    'b' | 'o' | '$' | '!' => {
+                        let count = count_buf.parse::<u32>().unwrap_or(1);
+                        count_buf.clear();
+                        match ch {
+                            'b' => cur_col += count,
+                            'o' => {
+                                for _ in 0..count {
+                                    let r = self.clone().row_add(row, cur_row);
+                                    let c = self.clone().col_add(col, cur_col);
+                                    let idx = self.get_index(r, c);
+                                    // Set unconditionally rather than going
+                                    // through the toggling `set_cells`, so
+                                    // loading onto a non-empty board (or an
+                                    // RLE with overlapping runs) still just
+                                    // sets live cells.
+                                    self.cells.set(idx, true);
+                                    cur_col += 1;
+                                }
+                            }
+                            '$' => {
+                                cur_row += count;
+                                cur_col = 0;
+                            }
+                            '!' => break 'lines,
+                            _ => unreachable!(),
+                        }
+                    }
+                    _ => log!("ignoring unexpected RLE character '{}'", ch),
+                }
+            }
+        }
+    }
+
+    // Run-length encodes the current grid back into the RLE format read by
+    // `load_rle`.
+    pub fn to_rle(&self) -> String {
+        let mut rows = Vec::with_capacity(self.height as usize);
+        for row in 0..self.height {
+            let mut runs: Vec<(u32, char)> = Vec::new();
+            for col in 0..self.width {
+                let idx = self.get_index(row, col);
+                let tag = if self.cells[idx] { 'o' } else { 'b' };
+                match runs.last_mut() {
+                    Some((len, last_tag)) if *last_tag == tag => *len += 1,
+                    _ => runs.push((1, tag)),
+                }
+            }
+            if let Some(&(_, 'b')) = runs.last() {
+                runs.pop();
+            }
+
+            let mut encoded = String::new();
+            for (len, tag) in runs {
+                if len > 1 {
+                    encoded.push_str(&len.to_string());
+                }
+                encoded.push(tag);
+            }
+            rows.push(encoded);
+        }
+
+        format!(
+            "x = {}, y = {}, rule = {}\n{}!",
+            self.width,
+            self.height,
+            self.rule.to_rule_string(),
+            rows.join("$\n")
+        )
+    }
+
     pub fn tick(&mut self) {
+        #[cfg(feature = "timing")]
+        let _t = Timer::new("Universe::tick");
+
+        if self.sparse_mode {
+            self.tick_sparse();
+        } else {
+            self.tick_dense();
+        }
+    }
+
+    // Visits and neighbour-counts every cell; cost is O(width * height).
+    fn tick_dense(&mut self) {
         let mut next = self.cells.clone();
         for row in 0..self.height {
             for col in 0..self.width {
                 let idx = self.get_index(row, col);
                 let cell = self.cells[idx];
-                let live_neighbours = self.live_neighbour_count(row, col);
-
-                next.set(
-                    idx,
-                    match (cell, live_neighbours) {
-                        // Rule 1: Any live cell with fewer than two neighbours dies
-                        // Rule 3: Any live cell with more than 3 neighbours dies
-                        (true, x) if x < 2 || x > 3 => {
-                            // log!("{} is dying", idx);
-                            false
-                        }
-                        // Rule 2: Any live cell with 2 or 3 neighbours lives on
-                        // (true, 2) | (true, 3) => true,
-                        // Rule 3: Any live cell with more than 3 neighbours dies
-                        // (true, x) if x > 3 => false,
-                        // Rule 4: Any dead cell with exactly 3 live neighbours becomes alive
-                        (false, 3) => {
-                            // log!("{} is coming to life", idx);
-                            true
-                        }
-                        // Everything else stays as is (i.e. dead)
-                        (otherwise, _) => otherwise,
-                    },
-                );
+                let n = self.live_neighbour_count(row, col) as usize;
+
+                let alive = if cell {
+                    self.rule.survive[n]
+                } else {
+                    self.rule.born[n]
+                };
+                next.set(idx, alive);
             }
         }
         self.cells = next;
     }
 
+    // Only visits neighbours of currently-live cells, so cost is
+    // proportional to population rather than grid area. Resyncs
+    // `live_cells` from `self.cells` first, since any of the drawing/load/
+    // randomize methods may have written to `self.cells` directly since the
+    // last tick; `self.cells` is then rebuilt from the stepped live set
+    // afterwards so `cells()`/`render()` stay unchanged.
+    fn tick_sparse(&mut self) {
+        self.live_cells = self.cells.ones().collect();
+
+        // Seed every live cell with a zero tally so rules with S0 (survival
+        // on zero neighbours) are evaluated correctly, not just cells that
+        // some neighbour's increment happens to touch.
+        let mut tally: HashMap<usize, u8> = self.live_cells.iter().map(|&idx| (idx, 0)).collect();
+        for &idx in &self.live_cells {
+            let row = idx as u32 / self.width;
+            let col = idx as u32 % self.width;
+            for delta_row in [self.height - 1, 0, 1].iter().cloned() {
+                for delta_col in [self.width - 1, 0, 1].iter().cloned() {
+                    if delta_row == 0 && delta_col == 0 {
+                        continue;
+                    }
+                    let neighbour_row = (row + delta_row) % self.height;
+                    let neighbour_col = (col + delta_col) % self.width;
+                    let neighbour_idx = self.get_index(neighbour_row, neighbour_col);
+                    *tally.entry(neighbour_idx).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut next_live = HashSet::new();
+        for (idx, count) in tally {
+            let n = count as usize;
+            let alive = if self.live_cells.contains(&idx) {
+                self.rule.survive[n]
+            } else {
+                self.rule.born[n]
+            };
+            if alive {
+                next_live.insert(idx);
+            }
+        }
+        self.live_cells = next_live;
+
+        self.cells.clear();
+        for &idx in &self.live_cells {
+            self.cells.set(idx, true);
+        }
+    }
+
+    // Toggles between the dense, whole-grid `tick` and the sparse,
+    // active-cell-only `tick`; dense random boards can keep using the
+    // default path. `tick_sparse` resyncs `live_cells` from the grid on
+    // every call, so no resync is needed here.
+    pub fn set_sparse_mode(&mut self, sparse: bool) {
+        self.sparse_mode = sparse;
+    }
+
+    // Runs a single `tick` and returns the elapsed time in milliseconds, for
+    // JS-side FPS graphs. Independent of the `timing` feature/console logging.
+    pub fn tick_timed(&mut self) -> f64 {
+        let performance = web_sys::window()
+            .expect("should have a window in this context")
+            .performance()
+            .expect("performance should be available");
+        let start = performance.now();
+        self.tick();
+        performance.now() - start
+    }
+
     pub fn width(&self) -> u32 {
         self.width
     }
     pub fn set_width(&mut self, width: u32) {
         self.width = width;
         let bitset = FixedBitSet::with_capacity((width * self.height) as usize);
-        self.cells = build_cells(bitset, width, self.height, UniverseContents::Empty);
+        self.cells = build_cells(
+            bitset,
+            width,
+            self.height,
+            UniverseContents::Empty,
+            &mut self.rng,
+        );
     }
     pub fn height(&self) -> u32 {
         self.height
@@ -181,18 +514,43 @@ impl Universe {
     pub fn set_height(&mut self, height: u32) {
         self.height = height;
         let bitset = FixedBitSet::with_capacity((self.width * height) as usize);
-        self.cells = build_cells(bitset, self.width, height, UniverseContents::Empty);
+        self.cells = build_cells(
+            bitset,
+            self.width,
+            height,
+            UniverseContents::Empty,
+            &mut self.rng,
+        );
     }
     pub fn cells(&self) -> *const u32 {
         self.cells.as_slice().as_ptr()
     }
     pub fn clear_cells(&mut self) {
         let bitset = FixedBitSet::with_capacity((self.width * self.height) as usize);
-        self.cells = build_cells(bitset, self.width, self.height, UniverseContents::Empty);
+        self.cells = build_cells(
+            bitset,
+            self.width,
+            self.height,
+            UniverseContents::Empty,
+            &mut self.rng,
+        );
     }
     pub fn randomize_cells(&mut self) {
         let bitset = FixedBitSet::with_capacity((self.width * self.height) as usize);
-        self.cells = build_cells(bitset, self.width, self.height, UniverseContents::Random);
+        self.cells = build_cells(
+            bitset,
+            self.width,
+            self.height,
+            UniverseContents::Random,
+            &mut self.rng,
+        );
+    }
+
+    // Reseeds the random-universe generator and immediately randomizes, so
+    // the resulting board can be reproduced later from the same seed.
+    pub fn randomize_cells_seeded(&mut self, seed: u64) {
+        self.rng = XorShiftRng::new(seed);
+        self.randomize_cells();
     }
 
     fn get_index(&self, row: u32, column: u32) -> usize {